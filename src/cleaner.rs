@@ -2,20 +2,62 @@ use anyhow::{Context, Result};
 use percent_encoding::percent_decode_str;
 use url::{form_urlencoded, Url};
 
+use crate::deamp;
+use crate::redirect::{self, RedirectBudget};
 use crate::rules::{CompiledHostRules, RuleSet};
 
 pub struct UrlCleaner {
     rules: RuleSet,
+    deamp_fetch: bool,
+    follow_redirects: Option<u32>,
 }
 
 impl UrlCleaner {
-    pub fn new(rules: RuleSet) -> Self { Self { rules } }
+    pub fn new(rules: RuleSet) -> Self {
+        Self { rules, deamp_fetch: false, follow_redirects: None }
+    }
+
+    /// Enable `--deamp-fetch`: fetch AMP pages that don't carry the
+    /// canonical URL in their address to recover it from the page itself.
+    pub fn with_deamp_fetch(mut self, enabled: bool) -> Self {
+        self.deamp_fetch = enabled;
+        self
+    }
+
+    /// Enable `--follow-redirects[=N]`: for hosts flagged with
+    /// `resolve_redirects`, follow up to `max_hops` redirect hops and clean
+    /// the final destination instead of the shortener link.
+    pub fn with_follow_redirects(mut self, max_hops: Option<u32>) -> Self {
+        self.follow_redirects = max_hops;
+        self
+    }
 
     pub fn clean(&self, raw: &str) -> Result<String> {
+        // A fresh budget per input, shared across the whole recursive
+        // clean_inner chain below — not re-created on every recursion — so
+        // a host that keeps handing back new resolve_redirects-flagged
+        // targets can't buy another max_hops worth of requests each hop.
+        let mut budget = self.follow_redirects.map(RedirectBudget::new);
+        self.clean_inner(raw, &mut budget)
+    }
+
+    fn clean_inner(&self, raw: &str, budget: &mut Option<RedirectBudget>) -> Result<String> {
         // Trim whitespace and surrounding < > often used in copy/pastes
         let trimmed = raw.trim().trim_start_matches('<').trim_end_matches('>');
         let mut url = Url::parse(trimmed).with_context(|| format!("Invalid URL: {}", raw))?;
 
+        // Host allow/deny lists gate everything else: denied hosts, and hosts
+        // outside a non-empty allow list, pass through byte-for-byte, before
+        // any fragment/AMP/param handling.
+        if let Some(host) = url.host_str() {
+            if self.rules.compile_deny_hosts()?.is_match(host) {
+                return Ok(trimmed.to_string());
+            }
+            if !self.rules.allow_hosts.is_empty() && !self.rules.compile_allow_hosts()?.is_match(host) {
+                return Ok(trimmed.to_string());
+            }
+        }
+
         // Some trackers put fake fragments that include params (e.g., #xtor=... or #ref=...)
         if let Some(frag) = url.fragment() {
             if frag.contains('=') {
@@ -24,12 +66,38 @@ impl UrlCleaner {
             }
         }
 
+        // AMP cache URLs carry the canonical URL in their path, so this is
+        // safe to unwrap offline regardless of --deamp-fetch.
+        if let Some(canonical) = deamp::unwrap_amp_cache(&url) {
+            return self.clean_inner(&canonical, budget);
+        }
+        if self.deamp_fetch && deamp::looks_like_amp(&url) {
+            let canonical = deamp::fetch_canonical(url.as_str()).ok().flatten();
+            let target = canonical.or_else(|| deamp::strip_amp_markers(&url).map(|u| u.into_string()));
+            if let Some(target) = target {
+                return self.clean_inner(&target, budget);
+            }
+        }
+
         // Host-specific unwrap logic
         if let Some(host) = url.host_str() {
             let hr = self.rules.matcher_for(host)?;
+
+            if hr.resolve_redirects {
+                if let Some(hop_budget) = budget.as_mut() {
+                    // A failed lookup (bad client config, etc.) just leaves the
+                    // URL as-is rather than dropping it from the output.
+                    if let Ok(resolved) = redirect::resolve(url.as_str(), hop_budget) {
+                        if resolved != url.as_str() {
+                            return self.clean_inner(&resolved, budget);
+                        }
+                    }
+                }
+            }
+
             if let Some(unwrapped) = try_unwrap(&url, &hr) {
                 // Recursively clean the inner URL with global rules applied as well
-                return self.clean(&unwrapped);
+                return self.clean_inner(&unwrapped, budget);
             }
         }
 
@@ -42,6 +110,7 @@ impl UrlCleaner {
         let host = url.host_str().unwrap_or("");
         let host_rules = self.rules.matcher_for(host)?;
         let global_globs = self.rules.compile_param_globs()?;
+        let global_regex = self.rules.compile_param_regex()?;
 
         let mut new_q: Vec<(String, String)> = Vec::new();
         let mut changed = false;
@@ -74,6 +143,14 @@ impl UrlCleaner {
                 continue;
             }
 
+            // Regex removals (global + host)
+            if global_regex.iter().any(|re| re.is_match(k))
+                || host_rules.remove_param_regex.iter().any(|re| re.is_match(k))
+            {
+                changed = true;
+                continue;
+            }
+
             new_q.push((k.clone(), v.clone()));
         }
 
@@ -126,6 +203,35 @@ mod tests {
         assert_eq!(u, "https://example.com/?x=1");
     }
 
+    #[test]
+    fn test_strip_param_regex() {
+        let mut rules = RuleSet::default();
+        rules.remove_param_regex.push("^utm_".into());
+        let c = UrlCleaner::new(rules);
+        let u = c.clean("https://example.com/?utm_weird=a&x=1").unwrap();
+        assert_eq!(u, "https://example.com/?x=1");
+    }
+
+    #[test]
+    fn test_deny_host_passes_through_untouched() {
+        let mut rules = RuleSet::builtin();
+        rules.deny_hosts.push("*.internal.example".into());
+        let c = UrlCleaner::new(rules);
+        let u = c.clean("https://host.internal.example/?utm_source=a").unwrap();
+        assert_eq!(u, "https://host.internal.example/?utm_source=a");
+    }
+
+    #[test]
+    fn test_allow_host_excludes_unlisted_hosts() {
+        let mut rules = RuleSet::builtin();
+        rules.allow_hosts.push("example.com".into());
+        let c = UrlCleaner::new(rules);
+        let cleaned = c.clean("https://example.com/?utm_source=a").unwrap();
+        assert_eq!(cleaned, "https://example.com/");
+        let untouched = c.clean("https://other.com/?utm_source=a").unwrap();
+        assert_eq!(untouched, "https://other.com/?utm_source=a");
+    }
+
     #[test]
     fn test_unwrap_google() {
         let c = UrlCleaner::new(RuleSet::builtin());
@@ -0,0 +1,141 @@
+//! Detects AMP delivery URLs and recovers the canonical article link.
+//!
+//! Google AMP cache URLs embed the real URL in their path, so those are
+//! unwrapped purely offline. Other AMP shapes (`/amp/` paths, `amp.`
+//! subdomains, `?amp=1`) don't carry the canonical URL in the URL itself;
+//! recovering it needs a page fetch, gated behind `--deamp-fetch`.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use url::Url;
+
+use crate::fetch;
+
+/// Reconstruct the canonical URL from a Google AMP cache URL
+/// (`*.cdn.ampproject.org/c/s/host/path` or `/c/host/path`), purely from
+/// the path — no network access.
+pub fn unwrap_amp_cache(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    if !host.ends_with(".cdn.ampproject.org") {
+        return None;
+    }
+    let path = url.path();
+    let mut rebuilt = path
+        .strip_prefix("/c/s/")
+        .map(|rest| format!("https://{rest}"))
+        .or_else(|| path.strip_prefix("/c/").map(|rest| format!("http://{rest}")))?;
+    if let Some(q) = url.query() {
+        rebuilt.push('?');
+        rebuilt.push_str(q);
+    }
+    Some(rebuilt)
+}
+
+/// True if `url` carries a non-cache AMP marker: an `amp.` subdomain, an
+/// `/amp/` path segment, a trailing `/amp`, a `.amp` suffix, or an
+/// `amp`/`outputType=amp` query marker.
+pub fn looks_like_amp(url: &Url) -> bool {
+    let host_is_amp = url.host_str().map(|h| h.starts_with("amp.")).unwrap_or(false);
+    let path = url.path();
+    let path_is_amp = path.contains("/amp/") || path.ends_with("/amp") || path.ends_with(".amp");
+    let query_is_amp = url
+        .query_pairs()
+        .any(|(k, v)| k == "amp" || (k == "outputType" && v == "amp"));
+    host_is_amp || path_is_amp || query_is_amp
+}
+
+/// Strip the AMP markers detected by [`looks_like_amp`] offline: drop the
+/// `amp.` subdomain, `/amp/` segments, a trailing `/amp`, a `.amp` suffix,
+/// and AMP query markers. Used under `--deamp-fetch` as the fallback when
+/// the fetch fails or finds no canonical link.
+pub fn strip_amp_markers(url: &Url) -> Option<Url> {
+    let mut out = url.clone();
+
+    if let Some(rest) = out.host_str().and_then(|h| h.strip_prefix("amp.")) {
+        let rest = rest.to_string();
+        out.set_host(Some(&rest)).ok()?;
+    }
+
+    let mut path = out.path().replace("/amp/", "/");
+    if let Some(stripped) = path.strip_suffix("/amp") {
+        path = if stripped.is_empty() { "/".to_string() } else { stripped.to_string() };
+    }
+    if let Some(stripped) = path.strip_suffix(".amp") {
+        path = stripped.to_string();
+    }
+    out.set_path(&path);
+
+    let kept: Vec<(String, String)> = out
+        .query_pairs()
+        .filter(|(k, v)| !(k == "amp" || (k == "outputType" && v == "amp")))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        out.set_query(None);
+    } else {
+        let mut ser = url::form_urlencoded::Serializer::new(String::new());
+        for (k, v) in kept {
+            ser.append_pair(&k, &v);
+        }
+        out.set_query(Some(&ser.finish()));
+    }
+
+    Some(out)
+}
+
+/// Fetch `page_url` and look for `<link rel="canonical" href="...">`,
+/// returning the canonical URL if the page has one. Only called when
+/// `--deamp-fetch` is set.
+pub fn fetch_canonical(page_url: &str) -> Result<Option<String>> {
+    let client = fetch::client(Duration::from_secs(10))?;
+    let body = client
+        .get(page_url)
+        .send()
+        .with_context(|| format!("Failed to fetch {}", page_url))?
+        .text()
+        .with_context(|| format!("Failed to read response body from {}", page_url))?;
+
+    let canonical_re =
+        Regex::new(r#"(?i)<link[^>]+rel=["']canonical["'][^>]+href=["']([^"']+)["']"#)
+            .expect("canonical link regex is valid");
+    Ok(canonical_re.captures(&body).map(|c| c[1].to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unwraps_amp_cache_c_s() {
+        let url = Url::parse("https://example-com.cdn.ampproject.org/c/s/example.com/a?x=1").unwrap();
+        assert_eq!(
+            unwrap_amp_cache(&url).unwrap(),
+            "https://example.com/a?x=1"
+        );
+    }
+
+    #[test]
+    fn test_unwraps_amp_cache_c() {
+        let url = Url::parse("https://example-com.cdn.ampproject.org/c/example.com/a").unwrap();
+        assert_eq!(unwrap_amp_cache(&url).unwrap(), "http://example.com/a");
+    }
+
+    #[test]
+    fn test_detects_amp_markers() {
+        assert!(looks_like_amp(&Url::parse("https://amp.example.com/a").unwrap()));
+        assert!(looks_like_amp(&Url::parse("https://example.com/amp/a").unwrap()));
+        assert!(looks_like_amp(&Url::parse("https://example.com/a/amp").unwrap()));
+        assert!(looks_like_amp(&Url::parse("https://example.com/a.amp").unwrap()));
+        assert!(looks_like_amp(&Url::parse("https://example.com/a?amp=1").unwrap()));
+        assert!(!looks_like_amp(&Url::parse("https://example.com/a").unwrap()));
+    }
+
+    #[test]
+    fn test_strips_markers_offline() {
+        let url = Url::parse("https://amp.example.com/amp/a?amp=1&x=1").unwrap();
+        let stripped = strip_amp_markers(&url).unwrap();
+        assert_eq!(stripped.as_str(), "https://example.com/a?x=1");
+    }
+}
@@ -0,0 +1,45 @@
+//! Shared HTTP client used by the optional network-backed features
+//! (de-AMP canonical lookup, redirect following, link verification).
+//! Centralized so every feature gets the same timeout and user agent.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::redirect::Policy;
+
+const USER_AGENT: &str = concat!("cleanshare/", env!("CARGO_PKG_VERSION"));
+
+fn builder(timeout: Duration) -> ClientBuilder {
+    Client::builder().user_agent(USER_AGENT).timeout(timeout)
+}
+
+/// Build a blocking HTTP client with the given timeout and CleanShare's
+/// user agent, following redirects with reqwest's default policy.
+pub fn client(timeout: Duration) -> Result<Client> {
+    builder(timeout).build().context("Failed to build HTTP client")
+}
+
+/// Like [`client`], but never follows redirects automatically — for
+/// callers that need to inspect and walk `3xx` hops themselves.
+pub fn client_no_redirect(timeout: Duration) -> Result<Client> {
+    builder(timeout)
+        .redirect(Policy::none())
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_builds_with_a_timeout() {
+        assert!(client(Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn test_client_no_redirect_builds_with_a_timeout() {
+        assert!(client_no_redirect(Duration::from_secs(5)).is_ok());
+    }
+}
@@ -1,15 +1,30 @@
 mod rules;
 mod cleaner;
+mod deamp;
+mod fetch;
+mod redirect;
+mod ublock;
+mod verify;
 
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, Write};
 use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 
 use anyhow::{Context, Result};
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Parser, ValueEnum};
 
 use crate::cleaner::UrlCleaner;
 use crate::rules::RuleSet;
+use crate::verify::VerifyRecord;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
 
 #[derive(Parser, Debug)]
 #[command(name = "cleanshare", version, about = "Clean trackers from URLs")]
@@ -33,6 +48,41 @@ struct Cli {
     /// Be verbose about non-fatal errors
     #[arg(short = 'v', long = "verbose", action = ArgAction::SetTrue)]
     verbose: bool,
+
+    /// Fetch AMP pages to recover the canonical URL when it isn't
+    /// reconstructable from the AMP URL alone (e.g. /amp/ paths, amp. hosts)
+    #[arg(long = "deamp-fetch", action = ArgAction::SetTrue)]
+    deamp_fetch: bool,
+
+    /// Only process URLs whose host matches one of these globs (repeatable)
+    #[arg(long = "allow-host", action = ArgAction::Append)]
+    allow_hosts: Vec<String>,
+
+    /// Never process URLs whose host matches one of these globs (repeatable)
+    #[arg(long = "deny-host", action = ArgAction::Append)]
+    deny_hosts: Vec<String>,
+
+    /// Follow redirects for known link shorteners (t.co, bit.ly, ...) and
+    /// clean the destination URL. Optionally caps the hop count (default 5).
+    #[arg(long = "follow-redirects", num_args = 0..=1, default_missing_value = "5")]
+    follow_redirects: Option<u32>,
+
+    /// Number of worker threads cleaning URLs concurrently (default: available parallelism)
+    #[arg(short = 'j', long = "jobs")]
+    jobs: Option<usize>,
+
+    /// Preserve input order in the output (default streams results as they complete)
+    #[arg(long = "ordered", action = ArgAction::SetTrue)]
+    ordered: bool,
+
+    /// After cleaning, verify the cleaned URL still resolves (issues a network request per URL)
+    #[arg(long = "verify", action = ArgAction::SetTrue)]
+    verify: bool,
+
+    /// Output format; `json` emits {input, cleaned, status, redirected_to,
+    /// original_status, broken_by_cleaning} records (used with --verify)
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
 }
 
 fn read_lines_from_file(path: &PathBuf) -> Result<impl Iterator<Item = String>> {
@@ -57,18 +107,147 @@ fn read_lines_from_stdin() -> Result<impl Iterator<Item = String>> {
         .filter(|s| !s.is_empty()))
 }
 
-fn write_output(lines: Vec<String>, output: &Option<PathBuf>) -> Result<()> {
-    let s = lines.join("\n");
-    if let Some(path) = output {
-        let mut f = File::create(path)
-            .with_context(|| format!("Failed to create output file {}", path.display()))?;
-        f.write_all(s.as_bytes())?;
-        f.write_all(b"\n")?;
+fn default_jobs() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+/// Output handling for `run_pipeline`, gathered into one struct so the
+/// function doesn't take a pile of trailing bools/enums separately.
+struct PipelineOptions<'a> {
+    ordered: bool,
+    verbose: bool,
+    verify: bool,
+    format: OutputFormat,
+    output: &'a Option<PathBuf>,
+}
+
+/// Stream input through `cleaner` using a pool of `jobs` worker threads,
+/// writing cleaned URLs to `opts.output` as they complete. `opts.ordered`
+/// buffers out-of-order results so output order matches input order; the
+/// default writes results as soon as they're ready, which is faster but
+/// unordered. Memory stays flat regardless of input size since the input is
+/// consumed lazily through a bounded channel rather than collected up front.
+///
+/// `build_inputs` is run on the producer thread rather than here, since
+/// stdin's lock (`StdinLock` holds a `MutexGuard`, which isn't `Send`)
+/// can't be built on this thread and then moved into another one.
+fn run_pipeline<F, I>(build_inputs: F, cleaner: Arc<UrlCleaner>, jobs: usize, opts: PipelineOptions) -> Result<()>
+where
+    F: FnOnce() -> Result<I> + Send + 'static,
+    I: Iterator<Item = String>,
+{
+    let PipelineOptions { ordered, verbose, verify: do_verify, format, output } = opts;
+
+    let (work_tx, work_rx) = mpsc::sync_channel::<(usize, String)>(jobs * 4);
+    let work_rx = Arc::new(Mutex::new(work_rx));
+    let (result_tx, result_rx) = mpsc::channel::<(usize, Option<String>)>();
+
+    let producer = thread::spawn(move || -> Result<()> {
+        let inputs = build_inputs()?;
+        for (i, line) in inputs.enumerate() {
+            if work_tx.send((i, line)).is_err() {
+                break;
+            }
+        }
+        Ok(())
+    });
+
+    let mut workers = Vec::with_capacity(jobs);
+    for _ in 0..jobs {
+        let work_rx = Arc::clone(&work_rx);
+        let result_tx = result_tx.clone();
+        let cleaner = Arc::clone(&cleaner);
+        workers.push(thread::spawn(move || loop {
+            let next = { work_rx.lock().unwrap().recv() };
+            let Ok((i, line)) = next else { break };
+            let cleaned = match cleaner.clean(&line) {
+                Ok(out) => out,
+                Err(e) => {
+                    if verbose {
+                        eprintln!("Skipping invalid URL '{}': {}", line, e);
+                    }
+                    if result_tx.send((i, None)).is_err() {
+                        break;
+                    }
+                    continue;
+                }
+            };
+
+            let out_line = if do_verify {
+                let result = verify::verify(&line, &cleaned);
+                match format {
+                    OutputFormat::Json => serde_json::to_string(&VerifyRecord {
+                        input: line.clone(),
+                        cleaned: cleaned.clone(),
+                        status: result.status,
+                        redirected_to: result.redirected_to.clone(),
+                        original_status: result.original_status,
+                        broken_by_cleaning: result.broken_by_cleaning,
+                    })
+                    .unwrap_or_else(|_| cleaned.clone()),
+                    OutputFormat::Text => {
+                        let status = result
+                            .status
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|| "ERR".to_string());
+                        let resolved = if result.broken_by_cleaning {
+                            "broken by cleaning"
+                        } else if result.ok {
+                            "ok"
+                        } else {
+                            "broken"
+                        };
+                        let redirect = result
+                            .redirected_to
+                            .map(|r| format!(" -> {}", r))
+                            .unwrap_or_default();
+                        format!("{} [{} {}]{}", cleaned, status, resolved, redirect)
+                    }
+                }
+            } else {
+                cleaned
+            };
+
+            if result_tx.send((i, Some(out_line))).is_err() {
+                break;
+            }
+        }));
+    }
+    drop(result_tx);
+
+    let mut writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(
+            File::create(path)
+                .with_context(|| format!("Failed to create output file {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout()),
+    };
+
+    if ordered {
+        let mut pending: HashMap<usize, Option<String>> = HashMap::new();
+        let mut next = 0usize;
+        for (i, cleaned) in result_rx {
+            pending.insert(i, cleaned);
+            while let Some(entry) = pending.remove(&next) {
+                if let Some(line) = entry {
+                    writeln!(writer, "{}", line)?;
+                }
+                next += 1;
+            }
+        }
     } else {
-        let mut stdout = io::stdout().lock();
-        stdout.write_all(s.as_bytes())?;
-        stdout.write_all(b"\n")?;
+        for (_, cleaned) in result_rx {
+            if let Some(line) = cleaned {
+                writeln!(writer, "{}", line)?;
+            }
+        }
     }
+
+    producer.join().expect("producer thread panicked")?;
+    for w in workers {
+        w.join().expect("worker thread panicked");
+    }
+
     Ok(())
 }
 
@@ -82,47 +261,46 @@ fn main() -> Result<()> {
             .with_context(|| format!("Failed to load rules file {}", path.display()))?;
         rules.merge(user_rules);
     }
+    rules.allow_hosts.extend(cli.allow_hosts.iter().cloned());
+    rules.deny_hosts.extend(cli.deny_hosts.iter().cloned());
 
-    let cleaner = UrlCleaner::new(rules);
+    let cleaner = Arc::new(
+        UrlCleaner::new(rules)
+            .with_deamp_fetch(cli.deamp_fetch)
+            .with_follow_redirects(cli.follow_redirects),
+    );
 
-    let mut inputs: Vec<String> = Vec::new();
-
-    // Collect URLs from -u
-    inputs.extend(cli.urls.iter().map(|s| s.to_string()));
-
-    // From -f file
-    if let Some(path) = &cli.file {
-        let iter = read_lines_from_file(path)?;
-        inputs.extend(iter);
-    }
-
-    // From STDIN if piped
-    if !atty::is(atty::Stream::Stdin) {
-        let iter = read_lines_from_stdin()?;
-        inputs.extend(iter);
-    }
-
-    if inputs.is_empty() {
+    let stdin_piped = !atty::is(atty::Stream::Stdin);
+    if cli.urls.is_empty() && cli.file.is_none() && !stdin_piped {
         eprintln!("No input URLs provided. Use -u, -f, or pipe input.");
         std::process::exit(2);
     }
 
-    // Process
-    let mut outputs: Vec<String> = Vec::with_capacity(inputs.len());
-    for line in inputs {
-        match cleaner.clean(&line) {
-            Ok(out) => outputs.push(out),
-            Err(e) => {
-                if cli.verbose {
-                    eprintln!("Skipping invalid URL '{}': {}", line, e);
-                }
-                // Skip invalid lines silently otherwise
-            }
-        }
-    }
+    let urls = cli.urls.clone();
+    let file = cli.file.clone();
+    let build_inputs = move || -> Result<Box<dyn Iterator<Item = String>>> {
+        let url_iter = urls.into_iter();
+        let file_iter: Box<dyn Iterator<Item = String>> = match &file {
+            Some(path) => Box::new(read_lines_from_file(path)?),
+            None => Box::new(std::iter::empty()),
+        };
+        let stdin_iter: Box<dyn Iterator<Item = String>> = if stdin_piped {
+            Box::new(read_lines_from_stdin()?)
+        } else {
+            Box::new(std::iter::empty())
+        };
+        Ok(Box::new(url_iter.chain(file_iter).chain(stdin_iter)))
+    };
 
-    write_output(outputs, &cli.output)?;
+    let jobs = cli.jobs.unwrap_or_else(default_jobs).max(1);
+    let opts = PipelineOptions {
+        ordered: cli.ordered,
+        verbose: cli.verbose,
+        verify: cli.verify,
+        format: cli.format,
+        output: &cli.output,
+    };
+    run_pipeline(build_inputs, cleaner, jobs, opts)?;
 
     Ok(())
 }
-
@@ -0,0 +1,162 @@
+//! Follows redirect chains for hosts flagged with `resolve_redirects`
+//! (link shorteners like `t.co`, `bit.ly`, `lnkd.in`), so trackers on the
+//! final destination get cleaned too. Only active under `--follow-redirects`.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::blocking::Client;
+use url::Url;
+
+use crate::fetch;
+
+/// Caps the total redirect-following work for one input URL: a hop count
+/// and a visited set, shared across every [`resolve`] call made while
+/// [`crate::cleaner::UrlCleaner::clean`] recurses on a single input — not
+/// reset per call, so a chain that keeps handing back fresh
+/// `resolve_redirects`-flagged hosts can't buy another `max_hops` worth of
+/// requests each time `clean` recurses.
+pub struct RedirectBudget {
+    remaining_hops: u32,
+    visited: HashSet<String>,
+}
+
+impl RedirectBudget {
+    pub fn new(max_hops: u32) -> Self {
+        Self { remaining_hops: max_hops, visited: HashSet::new() }
+    }
+}
+
+/// Follow `3xx` redirects starting at `url`, issuing HEAD first and
+/// falling back to GET when the HEAD response isn't a redirect, until `budget`
+/// is exhausted. Returns the final URL reached (or `url` unchanged if it
+/// never redirects, or if a hop can't be reached — a transient network
+/// failure here shouldn't drop an otherwise-cleanable URL).
+pub fn resolve(url: &str, budget: &mut RedirectBudget) -> Result<String> {
+    let client = fetch::client_no_redirect(Duration::from_secs(10))?;
+    Ok(resolve_with(url, budget, |current| next_hop(&client, current)))
+}
+
+fn resolve_with(url: &str, budget: &mut RedirectBudget, mut hop: impl FnMut(&str) -> Option<String>) -> String {
+    let mut current = url.to_string();
+
+    while budget.remaining_hops > 0 {
+        if !budget.visited.insert(current.clone()) {
+            break; // redirect loop
+        }
+        budget.remaining_hops -= 1;
+        match hop(&current) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+
+    current
+}
+
+/// Find the next redirect hop for `url`, or `None` if it doesn't redirect
+/// or the request fails outright (DNS, timeout, connection refused, ...).
+/// A failed request is treated the same as "no hop found" rather than
+/// propagated, so a transient network hiccup just leaves the URL as-is.
+fn next_hop(client: &Client, url: &str) -> Option<String> {
+    let resp = client.head(url).send().ok()?;
+    let resp = if resp.status().is_redirection() {
+        resp
+    } else {
+        // Some shorteners don't answer HEAD with the real redirect; retry with GET.
+        client.get(url).send().ok()?
+    };
+
+    if !resp.status().is_redirection() {
+        return None;
+    }
+
+    let location = resp
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok());
+    location.and_then(|loc| resolve_relative(url, loc))
+}
+
+fn resolve_relative(base: &str, location: &str) -> Option<String> {
+    Url::parse(base).ok()?.join(location).ok().map(|u| u.into_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_relative_joins_against_base() {
+        assert_eq!(
+            resolve_relative("https://t.co/abc", "/final").unwrap(),
+            "https://t.co/final"
+        );
+        assert_eq!(
+            resolve_relative("https://t.co/abc", "https://example.com/a").unwrap(),
+            "https://example.com/a"
+        );
+    }
+
+    #[test]
+    fn test_resolve_relative_rejects_bad_base() {
+        assert!(resolve_relative("not a url", "/final").is_none());
+    }
+
+    #[test]
+    fn test_resolve_with_follows_hops_until_none() {
+        let mut budget = RedirectBudget::new(10);
+        let result = resolve_with("https://t.co/1", &mut budget, |current| match current {
+            "https://t.co/1" => Some("https://t.co/2".to_string()),
+            "https://t.co/2" => Some("https://example.com/final".to_string()),
+            _ => None,
+        });
+        assert_eq!(result, "https://example.com/final");
+    }
+
+    #[test]
+    fn test_resolve_with_caps_at_max_hops() {
+        let mut calls = 0;
+        let mut budget = RedirectBudget::new(3);
+        let result = resolve_with("https://t.co/0", &mut budget, |_| {
+            calls += 1;
+            Some(format!("https://t.co/{calls}"))
+        });
+        assert_eq!(calls, 3);
+        assert_eq!(result, "https://t.co/3");
+    }
+
+    #[test]
+    fn test_resolve_with_breaks_on_redirect_loop() {
+        let mut calls = 0;
+        let mut budget = RedirectBudget::new(100);
+        let result = resolve_with("https://t.co/a", &mut budget, |current| {
+            calls += 1;
+            match current {
+                "https://t.co/a" => Some("https://t.co/b".to_string()),
+                "https://t.co/b" => Some("https://t.co/a".to_string()),
+                _ => None,
+            }
+        });
+        assert!(calls <= 3, "loop should be detected quickly, got {calls} calls");
+        assert!(result == "https://t.co/a" || result == "https://t.co/b");
+    }
+
+    #[test]
+    fn test_resolve_with_shares_budget_across_calls() {
+        let mut calls = 0;
+        let mut budget = RedirectBudget::new(3);
+        resolve_with("https://t.co/0", &mut budget, |_| {
+            calls += 1;
+            Some(format!("https://t.co/{calls}"))
+        });
+        // A second call sharing the same budget should pick up where the
+        // first left off instead of getting a fresh max_hops allowance.
+        resolve_with("https://t.co/3", &mut budget, |_| {
+            calls += 1;
+            Some(format!("https://t.co/{calls}"))
+        });
+        assert_eq!(calls, 3);
+    }
+}
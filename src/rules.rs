@@ -3,6 +3,7 @@ use std::path::Path;
 
 use anyhow::{bail, Context, Result};
 use globset::{Glob, GlobSet, GlobSetBuilder};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -11,10 +12,19 @@ pub struct RuleSet {
     pub remove_params: Vec<String>,
     /// Query params to remove (glob patterns, e.g., "utm_*")
     pub remove_param_globs: Vec<String>,
+    /// Query params to remove (regex over the param name)
+    #[serde(default)]
+    pub remove_param_regex: Vec<String>,
     /// Params to keep even if matched by remove rules
     pub keep_params: Vec<String>,
     /// Host-specific rules
     pub host_rules: Vec<HostRule>,
+    /// Host glob(s) to process; if non-empty, hosts matching none of these pass through untouched
+    #[serde(default)]
+    pub allow_hosts: Vec<String>,
+    /// Host glob(s) to never process; always pass through untouched
+    #[serde(default)]
+    pub deny_hosts: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -27,10 +37,17 @@ pub struct HostRule {
     pub remove_params: Vec<String>,
     /// Additional param globs to remove
     pub remove_param_globs: Vec<String>,
+    /// Additional params to remove (regex over the param name)
+    #[serde(default)]
+    pub remove_param_regex: Vec<String>,
     /// If true, drop all params except those in keep_params
     pub strip_all_params: Option<bool>,
     /// Params to keep for this host
     pub keep_params: Vec<String>,
+    /// If true, this host is a link shortener/redirector: follow its
+    /// redirect chain (under `--follow-redirects`) and clean the final URL
+    #[serde(default)]
+    pub resolve_redirects: bool,
 }
 
 impl RuleSet {
@@ -71,10 +88,7 @@ impl RuleSet {
             HostRule {
                 hosts: vec!["*.google.com".into()],
                 unwrap_params: vec!["url".into(), "q".into(), "u".into()],
-                remove_params: vec![],
-                remove_param_globs: vec![],
-                strip_all_params: None,
-                keep_params: vec![],
+                ..Default::default()
             },
             HostRule {
                 hosts: vec!["*.facebook.com".into(), "*.lm.facebook.com".into()],
@@ -91,6 +105,11 @@ impl RuleSet {
                 unwrap_params: vec!["q".into()],
                 ..Default::default()
             },
+            HostRule {
+                hosts: vec!["t.co".into(), "bit.ly".into(), "lnkd.in".into()],
+                resolve_redirects: true,
+                ..Default::default()
+            },
         ];
 
         s
@@ -99,19 +118,32 @@ impl RuleSet {
     pub fn merge(&mut self, other: RuleSet) {
         self.remove_params.extend(other.remove_params);
         self.remove_param_globs.extend(other.remove_param_globs);
+        self.remove_param_regex.extend(other.remove_param_regex);
         self.keep_params.extend(other.keep_params);
         self.host_rules.extend(other.host_rules);
+        self.allow_hosts.extend(other.allow_hosts);
+        self.deny_hosts.extend(other.deny_hosts);
     }
 
     pub fn from_path(path: &Path) -> Result<Self> {
-        let file = File::open(path)
-            .with_context(|| format!("Failed to open rules file {}", path.display()))?;
         let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
         let rules = match ext.to_ascii_lowercase().as_str() {
-            "yaml" | "yml" => serde_yaml::from_reader(file)
-                .with_context(|| "Failed to parse YAML rules")?,
-            "json" => serde_json::from_reader(file)
-                .with_context(|| "Failed to parse JSON rules")?,
+            "yaml" | "yml" => {
+                let file = File::open(path)
+                    .with_context(|| format!("Failed to open rules file {}", path.display()))?;
+                serde_yaml::from_reader(file).with_context(|| "Failed to parse YAML rules")?
+            }
+            "json" => {
+                let file = File::open(path)
+                    .with_context(|| format!("Failed to open rules file {}", path.display()))?;
+                serde_json::from_reader(file).with_context(|| "Failed to parse JSON rules")?
+            }
+            "txt" => {
+                let text = std::fs::read_to_string(path).with_context(|| {
+                    format!("Failed to open rules file {}", path.display())
+                })?;
+                crate::ublock::parse(&text)
+            }
             _ => bail!("Unsupported rules file extension: {}", ext),
         };
         Ok(rules)
@@ -142,14 +174,42 @@ impl RuleSet {
         let gs = builder.build()?;
         Ok(gs)
     }
+
+    pub(crate) fn compile_param_regex(&self) -> Result<Vec<Regex>> {
+        self.remove_param_regex
+            .iter()
+            .map(|pat| {
+                Regex::new(&format!("(?i){}", pat))
+                    .with_context(|| format!("Invalid param regex: {}", pat))
+            })
+            .collect()
+    }
+
+    pub(crate) fn compile_allow_hosts(&self) -> Result<GlobSet> {
+        compile_host_globs(&self.allow_hosts)
+    }
+
+    pub(crate) fn compile_deny_hosts(&self) -> Result<GlobSet> {
+        compile_host_globs(&self.deny_hosts)
+    }
+}
+
+fn compile_host_globs(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pat in patterns {
+        builder.add(Glob::new(pat).with_context(|| format!("Invalid host glob: {}", pat))?);
+    }
+    Ok(builder.build()?)
 }
 
 pub struct CompiledHostRules {
     pub unwrap_params: Vec<String>,
     pub remove_params: Vec<String>,
     pub remove_param_globs: GlobSet,
+    pub remove_param_regex: Vec<Regex>,
     pub strip_all_params: bool,
     pub keep_params: Vec<String>,
+    pub resolve_redirects: bool,
 }
 
 impl CompiledHostRules {
@@ -158,7 +218,9 @@ impl CompiledHostRules {
         let mut remove_params = Vec::new();
         let mut keep_params = Vec::new();
         let mut strip_all = false;
+        let mut resolve_redirects = false;
         let mut builder = GlobSetBuilder::new();
+        let mut remove_param_regex = Vec::new();
 
         for r in rules {
             unwrap_params.extend(r.unwrap_params.iter().cloned());
@@ -167,14 +229,30 @@ impl CompiledHostRules {
             if r.strip_all_params.unwrap_or(false) {
                 strip_all = true;
             }
+            if r.resolve_redirects {
+                resolve_redirects = true;
+            }
             for g in &r.remove_param_globs {
                 if let Ok(glob) = Glob::new(g) {
                     builder.add(glob);
                 }
             }
+            for pat in &r.remove_param_regex {
+                if let Ok(re) = Regex::new(&format!("(?i){}", pat)) {
+                    remove_param_regex.push(re);
+                }
+            }
         }
         let remove_param_globs = builder.build().unwrap_or_else(|_| GlobSetBuilder::new().build().unwrap());
-        Self { unwrap_params, remove_params, remove_param_globs, strip_all_params: strip_all, keep_params }
+        Self {
+            unwrap_params,
+            remove_params,
+            remove_param_globs,
+            remove_param_regex,
+            strip_all_params: strip_all,
+            keep_params,
+            resolve_redirects,
+        }
     }
 }
 
@@ -0,0 +1,160 @@
+//! Loader for uBlock Origin / AdGuard style filter lists.
+//!
+//! Only the `$removeparam` network-filter option is modeled; everything
+//! else (cosmetic `##` rules, plain network filters, comments) is ignored
+//! since CleanShare has no concept of blocking or element hiding.
+
+use crate::rules::{HostRule, RuleSet};
+
+/// Parse filter-list text into a [`RuleSet`].
+///
+/// Recognized shapes:
+/// - `||host.com^$removeparam=utm_source` — remove an exact param on a host.
+/// - `$removeparam=/^utm_/` — remove params matching a regex, globally.
+/// - `||host.com^$removeparam=/^utm_/` — remove params matching a regex, on a host.
+/// - `||host.com^$removeparam` — strip all params on that host. A bare
+///   `$removeparam` with no host anchor is skipped.
+/// - `@@||host.com^$removeparam=foo` — exception, keep `foo` on that host.
+///
+/// Lines carrying options other than `removeparam` are skipped, since the
+/// cleaner doesn't model network blocking or cosmetic rules.
+pub fn parse(text: &str) -> RuleSet {
+    let mut rules = RuleSet::default();
+
+    for raw_line in text.lines() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('!') || line.starts_with('[') {
+            continue;
+        }
+        if line.contains("##") || line.contains("#@#") {
+            continue; // cosmetic filter
+        }
+
+        let (is_exception, body) = match line.strip_prefix("@@") {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        let Some((pattern, options)) = body.split_once('$') else {
+            continue;
+        };
+
+        let mut removeparam: Option<Option<String>> = None; // Some(None) == bare `removeparam`
+        let mut unsupported = false;
+        for opt in options.split(',') {
+            match opt {
+                "removeparam" => removeparam = Some(None),
+                _ if opt.starts_with("removeparam=") => {
+                    removeparam = Some(Some(opt["removeparam=".len()..].to_string()))
+                }
+                _ => unsupported = true,
+            }
+        }
+        if unsupported {
+            continue;
+        }
+        let Some(value) = removeparam else { continue };
+
+        let host = pattern.strip_prefix("||").map(|h| h.trim_end_matches('^'));
+
+        match (host, value) {
+            (None, Some(v)) if is_regex(&v) => {
+                if !is_exception {
+                    rules.remove_param_regex.push(strip_regex_slashes(&v));
+                }
+            }
+            (None, Some(v)) => {
+                if !is_exception {
+                    rules.remove_params.push(v);
+                }
+            }
+            (None, None) => continue,
+            (Some(h), Some(v)) if is_regex(&v) => {
+                // Exceptions can't negate a regex rule with the exact-match
+                // `keep_params` list, so those are dropped; apply the rest.
+                if !is_exception {
+                    rules.host_rules.push(HostRule {
+                        hosts: vec![format!("*.{h}"), h.to_string()],
+                        remove_param_regex: vec![strip_regex_slashes(&v)],
+                        ..Default::default()
+                    });
+                }
+            }
+            (Some(h), Some(v)) => {
+                let hosts = vec![format!("*.{h}"), h.to_string()];
+                let hr = if is_exception {
+                    HostRule { hosts, keep_params: vec![v], ..Default::default() }
+                } else {
+                    HostRule { hosts, remove_params: vec![v], ..Default::default() }
+                };
+                rules.host_rules.push(hr);
+            }
+            (Some(h), None) => {
+                if is_exception {
+                    continue; // nothing to keep without a param name
+                }
+                rules.host_rules.push(HostRule {
+                    hosts: vec![format!("*.{h}"), h.to_string()],
+                    strip_all_params: Some(true),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    rules
+}
+
+fn is_regex(value: &str) -> bool {
+    value.len() >= 2 && value.starts_with('/') && value.ends_with('/')
+}
+
+fn strip_regex_slashes(value: &str) -> String {
+    value[1..value.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_host_removeparam() {
+        let rules = parse("||example.com^$removeparam=utm_source");
+        assert_eq!(rules.host_rules.len(), 1);
+        assert_eq!(rules.host_rules[0].hosts, vec!["*.example.com", "example.com"]);
+        assert_eq!(rules.host_rules[0].remove_params, vec!["utm_source"]);
+    }
+
+    #[test]
+    fn test_host_anchored_regex_removeparam() {
+        let rules = parse("||example.com^$removeparam=/^utm_/");
+        assert_eq!(rules.host_rules.len(), 1);
+        assert_eq!(rules.host_rules[0].hosts, vec!["*.example.com", "example.com"]);
+        assert_eq!(rules.host_rules[0].remove_param_regex, vec!["^utm_".to_string()]);
+    }
+
+    #[test]
+    fn test_global_regex_removeparam() {
+        let rules = parse("$removeparam=/^utm_/");
+        assert_eq!(rules.remove_param_regex, vec!["^utm_".to_string()]);
+    }
+
+    #[test]
+    fn test_bare_removeparam_strips_all() {
+        let rules = parse("||example.com^$removeparam");
+        assert_eq!(rules.host_rules[0].strip_all_params, Some(true));
+    }
+
+    #[test]
+    fn test_exception_populates_keep_params() {
+        let rules = parse("@@||example.com^$removeparam=foo");
+        assert_eq!(rules.host_rules[0].keep_params, vec!["foo"]);
+    }
+
+    #[test]
+    fn test_skips_unmodeled_lines() {
+        let rules = parse("||ads.example.com^$script,third-party\n##.banner-ad\n! a comment");
+        assert!(rules.host_rules.is_empty());
+        assert!(rules.remove_params.is_empty());
+    }
+}
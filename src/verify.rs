@@ -0,0 +1,137 @@
+//! `--verify` mode: after cleaning a URL, issue a lightweight HTTP request
+//! to confirm it still resolves, so users can catch params that turned out
+//! to be load-bearing (the cleaned URL now 404s/410s where the original
+//! worked). To tell "cleaning broke this" apart from "this was already
+//! broken", both the original and the cleaned URL are fetched and compared.
+
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use serde::Serialize;
+
+use crate::fetch;
+
+/// One `--verify` outcome for a single input line, the shape produced by
+/// `--format json`.
+#[derive(Debug, Serialize)]
+pub struct VerifyRecord {
+    pub input: String,
+    pub cleaned: String,
+    pub status: Option<u16>,
+    pub redirected_to: Option<String>,
+    pub original_status: Option<u16>,
+    pub broken_by_cleaning: bool,
+}
+
+pub struct VerifyResult {
+    pub status: Option<u16>,
+    pub ok: bool,
+    pub redirected_to: Option<String>,
+    pub original_status: Option<u16>,
+    pub broken_by_cleaning: bool,
+}
+
+/// Fetch both `input` and `cleaned`, reporting the cleaned URL's status,
+/// whether it's a `2xx`, and the final URL if redirected elsewhere, plus
+/// whether the original resolved fine while the cleaned one didn't — i.e.
+/// whether cleaning, not some unrelated outage, is what broke the link.
+/// Skips the second fetch when cleaning didn't change the URL.
+pub fn verify(input: &str, cleaned: &str) -> VerifyResult {
+    let Ok(client) = fetch::client(Duration::from_secs(10)) else {
+        return VerifyResult {
+            status: None,
+            ok: false,
+            redirected_to: None,
+            original_status: None,
+            broken_by_cleaning: false,
+        };
+    };
+
+    let cleaned_resp = fetch_status(&client, cleaned);
+
+    let status = cleaned_resp.as_ref().map(|r| r.status);
+    let ok = cleaned_resp.as_ref().map(|r| r.ok).unwrap_or(false);
+    let redirected_to = cleaned_resp.and_then(|r| r.redirected_to);
+    let (original_status, original_ok) = if input == cleaned {
+        (status, ok)
+    } else {
+        let original_resp = fetch_status(&client, input);
+        (original_resp.as_ref().map(|r| r.status), original_resp.map(|r| r.ok).unwrap_or(false))
+    };
+    let broken_by_cleaning = original_ok && !ok;
+
+    VerifyResult { status, ok, redirected_to, original_status, broken_by_cleaning }
+}
+
+struct FetchOutcome {
+    status: u16,
+    ok: bool,
+    redirected_to: Option<String>,
+}
+
+/// Issue a HEAD request against `url` (falling back to GET if the server
+/// rejects HEAD).
+fn fetch_status(client: &Client, url: &str) -> Option<FetchOutcome> {
+    let resp = client.head(url).send().ok().filter(|r| r.status() != reqwest::StatusCode::METHOD_NOT_ALLOWED);
+    let resp = match resp {
+        Some(r) => Some(r),
+        None => client.get(url).send().ok(),
+    };
+    resp.map(|r| {
+        let status = r.status().as_u16();
+        let ok = r.status().is_success();
+        let final_url = r.url().as_str();
+        let redirected_to = if final_url != url { Some(final_url.to_string()) } else { None };
+        FetchOutcome { status, ok, redirected_to }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_record_json_shape() {
+        let record = VerifyRecord {
+            input: "https://example.com/?utm_source=a".to_string(),
+            cleaned: "https://example.com/".to_string(),
+            status: Some(200),
+            redirected_to: None,
+            original_status: Some(200),
+            broken_by_cleaning: false,
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            json,
+            r#"{"input":"https://example.com/?utm_source=a","cleaned":"https://example.com/","status":200,"redirected_to":null,"original_status":200,"broken_by_cleaning":false}"#
+        );
+    }
+
+    #[test]
+    fn test_verify_record_json_shape_with_redirect() {
+        let record = VerifyRecord {
+            input: "https://t.co/abc".to_string(),
+            cleaned: "https://t.co/abc".to_string(),
+            status: Some(301),
+            redirected_to: Some("https://example.com/final".to_string()),
+            original_status: Some(301),
+            broken_by_cleaning: false,
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""redirected_to":"https://example.com/final""#));
+    }
+
+    #[test]
+    fn test_verify_record_json_shape_broken_by_cleaning() {
+        let record = VerifyRecord {
+            input: "https://example.com/?required=1".to_string(),
+            cleaned: "https://example.com/".to_string(),
+            status: Some(404),
+            redirected_to: None,
+            original_status: Some(200),
+            broken_by_cleaning: true,
+        };
+        let json = serde_json::to_string(&record).unwrap();
+        assert!(json.contains(r#""broken_by_cleaning":true"#));
+    }
+}